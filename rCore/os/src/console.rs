@@ -1,21 +1,133 @@
-use crate::sbi::console_putchar;
+use crate::sbi::console_write;
+use core::cell::UnsafeCell;
 use core::fmt::{self, Write};
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 // 我们在 console 子模块中编写 println! 宏。结构体 Stdout 不包含任何字段，因此它被称为类单元结构体（Unit-like structs，请参考 1 ）。 core::fmt::Write trait 包含一个用来实现 println! 宏很好用的 write_fmt 方法，为此我们准备为结构体 Stdout 实现 Write trait 。在 Write trait 中， write_str 方法必须实现，因此我们需要为 Stdout 实现这一方法，它并不难实现，只需遍历传入的 &str 中的每个字符并调用 console_putchar 就能将传入的整个字符串打印到屏幕上。
 
 // 在此之后 Stdout 便可调用 Write trait 提供的 write_fmt 方法并进而实现 print 函数。在声明宏（Declarative macros，参考 2 ） print! 和 println! 中会调用 print 函数完成输出。
-struct Stdout;
 
-impl Write for Stdout {
+/// 一个朴素的自旋锁：内核现在还是单核跑，但 `rust_main` 里一口气打印好几行
+/// `info!`/`debug!`，之后多核启动后这里也需要互斥，干脆现在就把锁补上。
+/// 标准库的 `Mutex` 在 `no_std` 下用不了，这里手写一个最小实现而不是引入外部 crate。
+struct SpinLock<T> {
+    locked: AtomicBool,
+    data: UnsafeCell<T>,
+}
+
+// `T: Send` 是必须的约束：拿到 `SpinLockGuard` 就能通过 `DerefMut` 把 `T` 移出
+// 锁（或者替换成别的值），如果 `T` 不是 `Send`，另一个 hart 就可能在它自己的
+// 线程里摸到一个本来只该由原来那个 hart 处理的值——和标准库 `Mutex<T>: Sync`
+// 同样要求 `T: Send` 是一个道理。当前唯一用到它的 `STDOUT_BUF` 存的是
+// `LineBuffer`（天然 `Send`），这里补上约束是为了后面多核场景下别的 `SpinLock<T>`
+// 也是可靠的，而不是依赖"现在只有这一种用法恰好安全"。
+unsafe impl<T: Send> Sync for SpinLock<T> {}
+
+impl<T> SpinLock<T> {
+    const fn new(data: T) -> Self {
+        SpinLock {
+            locked: AtomicBool::new(false),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    fn lock(&self) -> SpinLockGuard<'_, T> {
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            core::hint::spin_loop();
+        }
+        SpinLockGuard { lock: self }
+    }
+}
+
+struct SpinLockGuard<'a, T> {
+    lock: &'a SpinLock<T>,
+}
+
+impl<'a, T> Deref for SpinLockGuard<'a, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<'a, T> DerefMut for SpinLockGuard<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<'a, T> Drop for SpinLockGuard<'a, T> {
+    fn drop(&mut self) {
+        self.lock.locked.store(false, Ordering::Release);
+    }
+}
+
+/// 行缓冲：攒够一行（遇到 `\n`）或者缓冲区写满了再真正调用 SBI 输出，
+/// 避免像之前那样每个字符都单独走一次 legacy 的 `console_putchar` ecall。
+const LINE_BUF_SIZE: usize = 256;
+
+struct LineBuffer {
+    buf: [u8; LINE_BUF_SIZE],
+    len: usize,
+}
+
+impl LineBuffer {
+    const fn new() -> Self {
+        LineBuffer {
+            buf: [0; LINE_BUF_SIZE],
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, byte: u8) {
+        self.buf[self.len] = byte;
+        self.len += 1;
+        if byte == b'\n' || self.len == self.buf.len() {
+            self.flush();
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.len > 0 {
+            console_write(&self.buf[..self.len]);
+            self.len = 0;
+        }
+    }
+}
+
+static STDOUT_BUF: SpinLock<LineBuffer> = SpinLock::new(LineBuffer::new());
+
+/// 持有 `STDOUT_BUF` 锁的 `Write` 实现：一次 `print` 对应的 `write_fmt` 可能因为
+/// 格式化参数的个数触发好几次 `write_str` 回调，如果每次 `write_str` 各自加锁、
+/// 解锁，另一个 hart 的输出就可能插进同一条 `println!` 的几段字符串之间——这正是
+/// 加锁原本要避免的交错。把锁提到 `print` 这一层、在整个 `write_fmt` 期间持有同一个
+/// guard，才能让一条日志真正作为一个临界区整体输出。
+struct Stdout<'a> {
+    buf: SpinLockGuard<'a, LineBuffer>,
+}
+
+impl<'a> Write for Stdout<'a> {
     fn write_str(&mut self, s: &str) -> fmt::Result {
-        for c in s.chars() {
-            console_putchar(c as usize);
+        for &byte in s.as_bytes() {
+            self.buf.push(byte);
         }
         Ok(())
     }
 }
 
 pub fn print(args: fmt::Arguments) {
-    Stdout.write_fmt(args).unwrap();
+    let mut stdout = Stdout { buf: STDOUT_BUF.lock() };
+    stdout.write_fmt(args).unwrap();
+}
+
+/// 把行缓冲里还没遇到换行、尚未输出的内容强制冲刷出去，`shutdown` 之前必须调用，
+/// 否则最后一行不带换行符的日志会永远留在缓冲区里看不见。
+pub fn flush() {
+    STDOUT_BUF.lock().flush();
 }
 
 #[macro_export]
@@ -33,40 +145,94 @@ macro_rules! println {
 }
 
 // ============================================================================
-// 日志宏 - 使用 Cargo Features 实现条件编译
+// 日志宏 - 运行时可配置的日志级别
 // ============================================================================
 //
-// 实现方式：通过 #[cfg(feature = "log-xxx")] 在编译期控制日志输出
+// 之前的实现通过 #[cfg(feature = "log-xxx")] 在编译期决定保留哪个级别的日志，
+// 这意味着每次想换一个详细程度（比如从 INFO 换成 DEBUG）都要重新编译整个内核。
 //
-// 优点：
-//   - 零运行时开销（未启用的日志代码完全不存在）
-//   - 更小的二进制体积（只包含启用的日志代码）
-//   - 编译期确定，性能最优
+// 现在改为一个全局的 AtomicUsize 级别门限：每条 error!/warn!/info!/debug!/trace!
+// 在真正格式化、调用 console::print 之前，先和当前门限比较，级别不够就直接跳过，
+// 格式化参数也就不会被求值（惰性）。门限本身在 rust_main 启动时由 init_logging
+// 设置一次，之后可以被 SBI/设备树等运行时来源覆盖，而不需要再碰 Cargo feature。
 //
-// 使用方式：
+// 使用方式（make run LOG=INFO 仍然有效，只是不再走 feature 编译）：
 //   make run LOG=ERROR  - 只显示 ERROR
 //   make run LOG=WARN   - 显示 WARN + ERROR
 //   make run LOG=INFO   - 显示 INFO + WARN + ERROR
 //   make run LOG=DEBUG  - 显示 DEBUG + INFO + WARN + ERROR
 //   make run LOG=TRACE  - 显示所有日志
 //
-// 实现原理：
-//   #[cfg(feature = "log-info")] 会在编译期检查 feature 是否启用
-//   - 启用：保留代码，编译进二进制
-//   - 未启用：完全移除代码，不占用任何空间和性能
-//
 // ============================================================================
 
+/// 日志级别，数值越小优先级越高；当前门限之下（数值更大）的日志会被跳过。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Off = 0,
+    Error = 1,
+    Warn = 2,
+    Info = 3,
+    Debug = 4,
+    Trace = 5,
+}
+
+impl LogLevel {
+    /// 解析 `LOG` 环境变量/设备树字符串，未知取值回退为 `Info`。
+    pub fn from_str(s: &str) -> LogLevel {
+        match s {
+            "OFF" => LogLevel::Off,
+            "ERROR" => LogLevel::Error,
+            "WARN" => LogLevel::Warn,
+            "INFO" => LogLevel::Info,
+            "DEBUG" => LogLevel::Debug,
+            "TRACE" => LogLevel::Trace,
+            _ => LogLevel::Info,
+        }
+    }
+
+    /// 从 SBI 控制台非阻塞读到的一个字节里解析日志级别：`O/E/W/I/D/T` 分别对应
+    /// `Off/Error/Warn/Info/Debug/Trace`，其他字节（包括没有输入时的
+    /// `usize::MAX`）一律当作"没有等到合法输入"处理。和 `from_str` 读的编译期
+    /// `LOG` 环境变量不同，这个字节是当前这次运行时真实收到的输入，所以不需要
+    /// 重新编译内核就能在启动时换一个日志级别。
+    pub fn from_input_byte(byte: usize) -> Option<LogLevel> {
+        match u8::try_from(byte).ok()? {
+            b'O' => Some(LogLevel::Off),
+            b'E' => Some(LogLevel::Error),
+            b'W' => Some(LogLevel::Warn),
+            b'I' => Some(LogLevel::Info),
+            b'D' => Some(LogLevel::Debug),
+            b'T' => Some(LogLevel::Trace),
+            _ => None,
+        }
+    }
+}
+
+/// 默认门限为 INFO：未调用 `init_logging` 之前 error!/warn!/info! 仍然可见。
+static LOG_LEVEL: AtomicUsize = AtomicUsize::new(LogLevel::Info as usize);
+
+/// 设置全局日志门限。`rust_main` 启动时先用编译期 `LOG` 的值调用一次，
+/// 如果 SBI 控制台读到了合法的级别字节，再调用一次覆盖它。
+pub fn init_logging(level: LogLevel) {
+    LOG_LEVEL.store(level as usize, Ordering::Relaxed);
+}
+
+/// 供日志宏调用的门限检查：`level` 比当前门限更紧急（数值更小）或相等时才打印。
+pub fn log_enabled(level: LogLevel) -> bool {
+    (level as usize) <= LOG_LEVEL.load(Ordering::Relaxed)
+}
+
 /// ERROR 级别日志 - 红色
 /// 用于严重错误，总是应该显示
 #[macro_export]
 macro_rules! error {
     ($fmt: literal $(, $($arg: tt)+)?) => {
-        #[cfg(feature = "log-error")]
-        $crate::console::print(format_args!(
-            concat!("\x1b[31m[ERROR] ", $fmt, "\x1b[0m\n")
-            $(, $($arg)+)?
-        ));
+        if $crate::console::log_enabled($crate::console::LogLevel::Error) {
+            $crate::console::print(format_args!(
+                concat!("\x1b[31m[ERROR] ", $fmt, "\x1b[0m\n")
+                $(, $($arg)+)?
+            ));
+        }
     }
 }
 
@@ -75,11 +241,12 @@ macro_rules! error {
 #[macro_export]
 macro_rules! warn {
     ($fmt: literal $(, $($arg: tt)+)?) => {
-        #[cfg(feature = "log-warn")]
-        $crate::console::print(format_args!(
-            concat!("\x1b[93m[WARN ] ", $fmt, "\x1b[0m\n")
-            $(, $($arg)+)?
-        ));
+        if $crate::console::log_enabled($crate::console::LogLevel::Warn) {
+            $crate::console::print(format_args!(
+                concat!("\x1b[93m[WARN ] ", $fmt, "\x1b[0m\n")
+                $(, $($arg)+)?
+            ));
+        }
     }
 }
 
@@ -88,11 +255,12 @@ macro_rules! warn {
 #[macro_export]
 macro_rules! info {
     ($fmt: literal $(, $($arg: tt)+)?) => {
-        #[cfg(feature = "log-info")]
-        $crate::console::print(format_args!(
-            concat!("\x1b[34m[INFO ] ", $fmt, "\x1b[0m\n")
-            $(, $($arg)+)?
-        ));
+        if $crate::console::log_enabled($crate::console::LogLevel::Info) {
+            $crate::console::print(format_args!(
+                concat!("\x1b[34m[INFO ] ", $fmt, "\x1b[0m\n")
+                $(, $($arg)+)?
+            ));
+        }
     }
 }
 
@@ -101,11 +269,12 @@ macro_rules! info {
 #[macro_export]
 macro_rules! debug {
     ($fmt: literal $(, $($arg: tt)+)?) => {
-        #[cfg(feature = "log-debug")]
-        $crate::console::print(format_args!(
-            concat!("\x1b[32m[DEBUG] ", $fmt, "\x1b[0m\n")
-            $(, $($arg)+)?
-        ));
+        if $crate::console::log_enabled($crate::console::LogLevel::Debug) {
+            $crate::console::print(format_args!(
+                concat!("\x1b[32m[DEBUG] ", $fmt, "\x1b[0m\n")
+                $(, $($arg)+)?
+            ));
+        }
     }
 }
 
@@ -114,10 +283,11 @@ macro_rules! debug {
 #[macro_export]
 macro_rules! trace {
     ($fmt: literal $(, $($arg: tt)+)?) => {
-        #[cfg(feature = "log-trace")]
-        $crate::console::print(format_args!(
-            concat!("\x1b[90m[TRACE] ", $fmt, "\x1b[0m\n")
-            $(, $($arg)+)?
-        ));
+        if $crate::console::log_enabled($crate::console::LogLevel::Trace) {
+            $crate::console::print(format_args!(
+                concat!("\x1b[90m[TRACE] ", $fmt, "\x1b[0m\n")
+                $(, $($arg)+)?
+            ));
+        }
     }
-}
\ No newline at end of file
+}
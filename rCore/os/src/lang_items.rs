@@ -54,5 +54,8 @@ fn panic(info: &PanicInfo) -> ! {
     } else {
         println!("Panicked: {}", info.message());  // ✅ 正确：同上
     }
+    // println! 写进的是行缓冲；它自带的换行会把缓冲区（连同之前还没换行的内容）
+    // 一起冲刷出去。关机前再 flush 一次，保证 shutdown 时缓冲区里没有残留。
+    crate::console::flush();
     shutdown(true)
 }
\ No newline at end of file
@@ -83,6 +83,17 @@ unsafe extern "C" {
 pub fn rust_main() -> ! {
     // 永不返回
     clear_bss(); // 清零 BSS 段
+
+    // 运行时日志门限：先用编译期传入的 LOG 环境变量给一个默认值（内核还没有
+    // cmdline/设备树解析能力，没法在启动更早的阶段拿到别的输入）。随后非阻塞地
+    // 从 SBI 控制台读一个字节：如果它是 O/E/W/I/D/T 中的一个就覆盖默认门限——
+    // `sbi::console_getchar` 读到的是这一次运行时真实收到的输入，所以换级别
+    // 不需要重新编译内核，和之前 #[cfg(feature = "log-xxx")] 的编译期方案不同。
+    console::init_logging(console::LogLevel::from_str(option_env!("LOG").unwrap_or("INFO")));
+    if let Some(level) = console::LogLevel::from_input_byte(sbi::console_getchar()) {
+        console::init_logging(level);
+    }
+
     println!("this is a test");
     info!("this is a info");
     warn!("warn");
@@ -4,6 +4,33 @@ pub fn console_putchar(c: usize) {
     sbi_rt::legacy::console_putchar(c);
 }
 
+/// 把一段已经攒好的字节批量写到控制台，供 `console::flush` 在缓冲区满/遇到换行时调用。
+/// 走非 legacy 的 DBCN（Debug Console，chapter 12）扩展的 CONSOLE_WRITE，这样一整段
+/// 字节是一次 ecall 写下去的，而不是像 legacy 的 `console_putchar` 那样一个字符一次
+/// ecall。内核现在还没有分页，虚拟地址就是物理地址，所以直接用 `bytes.as_ptr()`
+/// 当作物理地址传给 SBI。DBCN 的 CONSOLE_WRITE 是非阻塞调用，可能只写一部分，
+/// 所以这里循环到写完为止；如果某次调用没有任何进展（返回错误，或者 SBI 实现根本
+/// 不支持 DBCN），就退回到 legacy 的逐字节输出，保证老的 SBI 实现上也能看到输出。
+pub fn console_write(bytes: &[u8]) {
+    let mut written = 0;
+    while written < bytes.len() {
+        let chunk = sbi_rt::Physical::new(
+            bytes.len() - written,
+            bytes.as_ptr() as usize + written,
+            0,
+        );
+        match sbi_rt::console_write(chunk).ok() {
+            Some(n) if n > 0 => written += n,
+            _ => {
+                for &b in &bytes[written..] {
+                    console_putchar(b as usize);
+                }
+                return;
+            }
+        }
+    }
+}
+
 pub fn shutdown(failure: bool) -> ! {
     use sbi_rt::{NoReason, Shutdown, SystemFailure, system_reset};
     if !failure {
@@ -14,4 +41,82 @@ pub fn shutdown(failure: bool) -> ! {
     unreachable!()
 }
 // sbi_rt 是如何调用 SBI 服务的
-// SBI spec 的 Chapter 3 介绍了服务的调用方法：只需将要调用功能的拓展 ID 和功能 ID 分别放在 a7 和 a6 寄存器中，并按照 RISC-V 调用规范将参数放置在其他寄存器中，随后执行 ecall 指令即可。这会将控制权转交给 RustSBI 并由 RustSBI 来处理请求，处理完成后会将控制权交还给内核。返回值会被保存在 a0 和 a1 寄存器中。在本书的第二章中，我们会手动编写汇编代码来实现类似的过程。
\ No newline at end of file
+// SBI spec 的 Chapter 3 介绍了服务的调用方法：只需将要调用功能的拓展 ID 和功能 ID 分别放在 a7 和 a6 寄存器中，并按照 RISC-V 调用规范将参数放置在其他寄存器中，随后执行 ecall 指令即可。这会将控制权转交给 RustSBI 并由 RustSBI 来处理请求，处理完成后会将控制权交还给内核。返回值会被保存在 a0 和 a1 寄存器中。在本书的第二章中，我们会手动编写汇编代码来实现类似的过程。
+
+// ============================================================================
+// 把 sbi 模块从"只封装 console_putchar/shutdown"扩展成一个更完整的 SBI 门面，
+// 为后面章节要用到的定时器中断调度和多核启动打好地基。除了 `console_getchar`
+// （`rust_main` 启动时用它读日志级别）之外，这一批接口目前还没有调用方
+// （定时器子系统、SMP 启动都是后面章节的内容），先各自标上
+// allow(dead_code) 压住警告，等对应子系统接入后再去掉。
+// ============================================================================
+
+/// 对 `SbiRet` 的一层瘦封装：成功时拿到 `value`，失败时把 SBI 规范里定义的
+/// 错误码（负数）原样带出来，调用方不需要关心 a0/a1 两个寄存器的约定。
+#[allow(dead_code)]
+pub type SbiResult = Result<usize, isize>;
+
+#[allow(dead_code)]
+fn to_result(ret: sbi_rt::SbiRet) -> SbiResult {
+    ret.ok().ok_or(ret.error as isize)
+}
+
+/// TIME 扩展：为下一次定时器中断设置一个绝对时间 `stime_value`（单位是
+/// mtime 计数），同时会清除当前待处理的时钟中断标志位。之后的定时器子系统
+/// 会在每次时钟中断里重新调用它来安排下一次中断。
+#[allow(dead_code)]
+pub fn set_timer(stime_value: u64) -> SbiResult {
+    to_result(sbi_rt::set_timer(stime_value))
+}
+
+/// legacy 的 CONSOLE_GETCHAR 扩展：没有输入时返回 `usize::MAX`（也就是按
+/// `isize` 解读的 -1），否则返回读到的那个字节。非阻塞调用，`rust_main` 用它
+/// 在启动时尝试读一个日志级别字符，读不到就保留编译期的默认门限。
+pub fn console_getchar() -> usize {
+    #[allow(deprecated)]
+    sbi_rt::legacy::console_getchar()
+}
+
+/// HSM 扩展：在目标 hart 上以 supervisor 态从 `start_addr` 开始执行，`opaque`
+/// 会被放进目标 hart 的 a1 寄存器，通常用来传一个指向启动参数的指针。
+#[allow(dead_code)]
+pub fn hart_start(hartid: usize, start_addr: usize, opaque: usize) -> SbiResult {
+    to_result(sbi_rt::hart_start(hartid, start_addr, opaque))
+}
+
+/// HSM 扩展：停止当前调用所在的 hart，正常情况下这个调用不会返回。
+#[allow(dead_code)]
+pub fn hart_stop() -> SbiResult {
+    to_result(sbi_rt::hart_stop())
+}
+
+/// HSM 扩展里 `hart_get_status` 返回的状态码，对应 SBI spec chapter 9.3 的表格。
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HartState {
+    Started,
+    Stopped,
+    StartPending,
+    StopPending,
+    /// 规范之外的取值，原样保留数值以便上层打印/调试。
+    Unknown(usize),
+}
+
+impl From<usize> for HartState {
+    fn from(value: usize) -> Self {
+        match value {
+            0 => HartState::Started,
+            1 => HartState::Stopped,
+            2 => HartState::StartPending,
+            3 => HartState::StopPending,
+            other => HartState::Unknown(other),
+        }
+    }
+}
+
+/// HSM 扩展：查询指定 hart 当前的状态，为后续多核启动时判断"这个 hart 是否
+/// 已经跑起来了"提供依据。
+#[allow(dead_code)]
+pub fn hart_get_status(hartid: usize) -> Result<HartState, isize> {
+    to_result(sbi_rt::hart_get_status(hartid)).map(HartState::from)
+}
\ No newline at end of file
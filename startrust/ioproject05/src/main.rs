@@ -1,7 +1,11 @@
 use std::env;
 use std::process;
 
-use ioproject05::Config;
+// 这里原来是 `use ioproject05::Config;`，但这个 crate 其实从来没有自己的
+// src/lib.rs，那一行一直指向一个不存在的模块。ioproject05/06/07 三份 minigrep
+// 几乎是同一份实现的三个版本，现在统一改成直接依赖 ioproject07 里已经长出来的
+// `Config::builder()`/`run`，顺便把这个历史遗留的坏引用也修好。
+use ioproject07::Config;
 // 读取参数值
 // 为了确保 minigrep 能够获取传递给它的命令行参数的值，
 // 我们需要一个 Rust 标准库提供的函数，也就是 std::env::args。
@@ -17,15 +21,16 @@ fn main() {
     // let filename = &args[2];
     // let contents = fs::read_to_string(filename).expect("Something went wrong reading the file");
 
-    let args: Vec<String> = env::args().collect();
+    let mut args = env::args();
+    args.next();
 
-    let config = Config::new(&args).unwrap_or_else(|err| {
+    let config = Config::builder().parse(args).unwrap_or_else(|err| {
         // println!("Problem parsing arguments: {}", err);
         eprintln!("Problem parsing arguments: {}", err);
         process::exit(1);
     });
 
-    if let Err(e) = ioproject05::run(config) {
+    if let Err(e) = ioproject07::run(config) {
         // println!("Application error: {}", e);
         eprintln!("Application error: {}", e);
         // epirntln!将输出打印到标准错误流,println将输出打印到标准输出流
@@ -2,6 +2,8 @@ use std::env;
 use std::error::Error;
 use std::fs;
 
+// main.rs 现在直接用 ioproject07::Config::builder()/run 了，这里的 Config/run/
+// search 不再是可执行文件实际走的路径，保留下来作为这一章节的历史实现和测试。
 pub struct Config {
     pub query: String,
     pub filename: String,
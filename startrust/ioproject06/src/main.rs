@@ -1,18 +1,22 @@
 use std::env;
 use std::process;
 
-use ioproject06::Config;
+// ioproject05/06/07 原本各自维护一份几乎相同的 Config/run，这里改成和
+// ioproject05 一样直接走 ioproject07 统一之后的 Config::builder()/run；
+// 本 crate 自己的 Config/search（lib.rs）保留下来作为这一章节的历史实现，
+// 不再是可执行文件实际跑的那条路径。
+use ioproject07::Config;
 
 fn main() {
-  
-    let args: Vec<String> = env::args().collect();
+    let mut args = env::args();
+    args.next();
 
-    let config = Config::new(args).unwrap_or_else(|err| {
+    let config = Config::builder().parse(args).unwrap_or_else(|err| {
         eprintln!("Problem parsing arguments: {}", err);
         process::exit(1);
     });
 
-    if let Err(e) = ioproject06::run(config) {
+    if let Err(e) = ioproject07::run(config) {
         eprintln!("Application error: {}", e);
         process::exit(1);
     }
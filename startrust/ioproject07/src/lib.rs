@@ -1,77 +1,533 @@
+use std::collections::VecDeque;
 use std::error::Error;
+use std::fmt;
 use std::fs;
 use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
 
+use regex::{Regex, RegexBuilder};
+
+#[derive(Debug)]
 pub struct Config {
     pub query: String,
-    pub filename: String,
+    pub filenames: Vec<String>,
     pub case_sensitive: bool,
+    pub regex: bool,
+    pub recursive: bool,
+    pub line_numbers: bool,
+    pub before_context: usize,
+    pub after_context: usize,
 }
 
 
-// 此处的new由ioproject05修改而来，起初这里需要 clone 的原因是参数 args 中有一个 String 元素的 slice，
-// 而 new 函数并不拥有 args。为了能够返回 Config 实例的所有权，我们需要克隆 Config 中字段 query 和 filename 的值，这样 Config 实例就能拥有这些值。
-// 在学习了迭代器之后，我们可以将 new 函数改为获取一个有所有权的迭代器作为参数而不是借用 slice。我们将使用迭代器功能之前检查 slice 长度和索引特定位置的代码。
-// 这会明确 Config::new 的工作因为迭代器会负责访问这些值。
-// 一旦 Config::new 获取了迭代器的所有权并不再使用借用的索引操作，就可以将迭代器中的 String 值移动到 Config 中，而不是调用 clone 分配新的空间。
-// env::args 函数的标准库文档显示，它返回的迭代器的类型为 std::env::Args
-// 因为我们拥有 args 的所有权，并且将通过对其进行迭代来改变 args，所以我们可以将 mut 关键字添加到 args 参数的规范中以使其可变。
-// 接下来，我们将修改 Config::new 的内容。标准库文档还提到 std::env::Args 实现了 Iterator trait，因此我们知道可以对其调用 next 方法！
-// 请记住 env::args 返回值的第一个值是程序的名称。我们希望忽略它并获取下一个值，所以首先调用 next 并不对返回值做任何操作。
+// ioproject05 用 `&[String]`、ioproject06 用 `Vec<String>`、这个crate自己原来用
+// `std::env::Args`——三份 Config::new 几乎一模一样，区别只在愿意接受哪种参数
+// 容器，而且都只支持"第一个参数是 query、第二个是 filename"这种固定位置解析。
+// 现在统一成 `Config::builder().parse(args)`：`parse` 接受任意
+// `IntoIterator<Item = String>`，所以 env::args()、`vec!["foo".to_string(), ...]`
+// 或者测试里手写的迭代器都能直接传进来；同时支持
+// --ignore-case/--regex/--recursive/-n 这些 flag 穿插在位置参数之间，遇到不认识
+// 的 flag 或者缺值的 -A/-B/-C 会报出具体是哪个 flag 出的问题。
+#[derive(Debug, Default)]
+pub struct ConfigBuilder {
+    ignore_case: bool,
+    regex: bool,
+    recursive: bool,
+    line_numbers: bool,
+    before_context: usize,
+    after_context: usize,
+}
+
 impl Config {
-    pub fn new(mut args:std::env::Args ) -> Result<Config, &'static str> {
-        args.next();
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::default()
+    }
+}
 
-        let query = match args.next() {
-            Some(arg) => arg,
-            None => return Err("Didn't get a query string"),
-        };
+impl ConfigBuilder {
+    /// 解析命令行参数（不包含程序名那一项）。位置参数里第一个是 query，
+    /// 之后的都是要搜索的路径；其余的都按 flag 处理。
+    pub fn parse<I: IntoIterator<Item = String>>(mut self, args: I) -> Result<Config, ConfigError> {
+        let mut positional = Vec::new();
+        let mut args = args.into_iter();
 
-        let filename = match args.next() {
-            Some(arg) => arg,
-            None => return Err("Didn't get a file name"),
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-i" | "--ignore-case" => self.ignore_case = true,
+                "-e" | "--regex" => self.regex = true,
+                "-r" | "--recursive" => self.recursive = true,
+                "-n" | "--line-number" => self.line_numbers = true,
+                "-A" | "--after-context" => {
+                    self.after_context = Self::parse_context_value(&arg, args.next())?;
+                    self.line_numbers = true;
+                }
+                "-B" | "--before-context" => {
+                    self.before_context = Self::parse_context_value(&arg, args.next())?;
+                    self.line_numbers = true;
+                }
+                "-C" | "--context" => {
+                    let n = Self::parse_context_value(&arg, args.next())?;
+                    self.before_context = n;
+                    self.after_context = n;
+                    self.line_numbers = true;
+                }
+                flag if flag.starts_with('-') && flag.len() > 1 => {
+                    return Err(ConfigError::UnknownFlag(flag.to_string()));
+                }
+                _ => positional.push(arg),
+            }
+        }
+
+        let mut positional = positional.into_iter();
+        let query = positional.next().ok_or(ConfigError::MissingQuery)?;
+        let filenames: Vec<String> = positional.collect();
+        if filenames.is_empty() {
+            return Err(ConfigError::MissingFilename);
+        }
+
+        // --ignore-case 优先于环境变量；没有显式传 flag 的话，CASE_INSENSITIVE
+        // 这个环境变量继续作为默认值，和三份旧实现的行为保持一致。
+        let case_sensitive = if self.ignore_case {
+            false
+        } else {
+            env::var("CASE_INSENSITIVE").is_err()
         };
 
-        let case_sensitive = env::var("CASE_INSENSITIVE").is_err();
+        Ok(Config {
+            query,
+            filenames,
+            case_sensitive,
+            regex: self.regex,
+            recursive: self.recursive,
+            line_numbers: self.line_numbers,
+            before_context: self.before_context,
+            after_context: self.after_context,
+        })
+    }
 
-        Ok(Config { query, filename, case_sensitive })
+    /// `-A`/`-B`/`-C` 后面都跟着一个表示行数的数字参数，这里统一校验并给出带着
+    /// 具体 flag 名字的错误信息，而不是笼统的 "not enough arguments"。
+    fn parse_context_value(flag: &str, value: Option<String>) -> Result<usize, ConfigError> {
+        let value = value.ok_or_else(|| ConfigError::MissingContextValue(flag.to_string()))?;
+        value
+            .parse::<usize>()
+            .map_err(|_| ConfigError::InvalidContextValue(flag.to_string()))
     }
 }
 
-pub fn run(config: Config) -> Result<(), Box<dyn Error>> {
-    let contents = fs::read_to_string(config.filename)?;
+/// 解析命令行参数时可能遇到的错误，每一种都带着出问题的 flag/参数名字，
+/// 而不是一句笼统的 "not enough arguments"。
+#[derive(Debug)]
+pub enum ConfigError {
+    MissingQuery,
+    MissingFilename,
+    UnknownFlag(String),
+    MissingContextValue(String),
+    InvalidContextValue(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::MissingQuery => write!(f, "Didn't get a query string"),
+            ConfigError::MissingFilename => write!(f, "Didn't get a file name"),
+            ConfigError::UnknownFlag(flag) => write!(f, "unrecognized flag: {}", flag),
+            ConfigError::MissingContextValue(flag) => write!(f, "missing line count for {}", flag),
+            ConfigError::InvalidContextValue(flag) => write!(f, "{} expects a number", flag),
+        }
+    }
+}
 
-    let results = if config.case_sensitive {
-        search(&config.query, &contents)
-    } else {
-        search_case_insensitive(&config.query, &contents)
-    };
+impl Error for ConfigError {}
 
-    for line in results {
-        println!("{}", line);
+/// 编译好的匹配器：要么是子串（不区分大小写时预先一次性小写化），要么是预先编译好的正则表达式。
+/// `run` 在进入逐行扫描之前构造一次，这样 `search`/`search_case_insensitive`
+/// 就不需要在每一行都重新编译模式。
+pub enum Matcher {
+    Substring(String),
+    Regex(Regex),
+}
+
+impl Matcher {
+    pub fn new(query: &str, case_sensitive: bool, regex: bool) -> Result<Matcher, Box<dyn Error>> {
+        if regex {
+            let re = RegexBuilder::new(query)
+                .case_insensitive(!case_sensitive)
+                .build()?;
+            Ok(Matcher::Regex(re))
+        } else if case_sensitive {
+            Ok(Matcher::Substring(query.to_string()))
+        } else {
+            Ok(Matcher::Substring(query.to_lowercase()))
+        }
     }
 
-    Ok(())
+    fn is_match(&self, line: &str) -> bool {
+        match self {
+            Matcher::Substring(query) => line.contains(query.as_str()),
+            Matcher::Regex(re) => re.is_match(line),
+        }
+    }
+}
+
+fn line_matches(matcher: &Matcher, line: &str, case_sensitive: bool) -> bool {
+    match matcher {
+        Matcher::Substring(query) if !case_sensitive => line.to_lowercase().contains(query.as_str()),
+        _ => matcher.is_match(line),
+    }
+}
+
+/// 找出所有命中行，连同它们的 1-based 行号一起返回。`search`/`search_case_insensitive`
+/// 和 `build_match_groups` 都基于这一个函数，行号相关的改动只需要改这一处。
+fn search_with_lines<'a>(matcher: &Matcher, contents: &'a str, case_sensitive: bool) -> Vec<(usize, &'a str)> {
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line_matches(matcher, line, case_sensitive))
+        .map(|(i, line)| (i + 1, line))
+        .collect()
+}
+
+/// 一行输出：行号、原文，以及它是命中行本身还是上下文行（用来决定打印
+/// `lineno:line` 还是 `lineno-line`）。
+pub struct ContextLine {
+    pub lineno: usize,
+    pub text: String,
+    pub is_match: bool,
+}
+
+/// 一组连续（或窗口重叠后合并）的输出行；组与组之间在打印时用 `--` 分隔。
+pub struct MatchGroup {
+    pub lines: Vec<ContextLine>,
+}
+
+/// 找出所有命中行，再把每一行命中扩展成 `[lineno-before, lineno+after]` 的窗口，
+/// 并把互相重叠或相邻的窗口合并成一组，这样一次命中附近连续出现的多个匹配
+/// 不会被打印成好几段重复的上下文。
+fn build_match_groups(
+    matcher: &Matcher,
+    contents: &str,
+    case_sensitive: bool,
+    before: usize,
+    after: usize,
+) -> Vec<MatchGroup> {
+    let all_lines: Vec<&str> = contents.lines().collect();
+
+    let matched_lines: Vec<usize> = search_with_lines(matcher, contents, case_sensitive)
+        .into_iter()
+        .map(|(lineno, _)| lineno)
+        .collect();
+
+    if matched_lines.is_empty() {
+        return Vec::new();
+    }
+
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &lineno in &matched_lines {
+        let start = lineno.saturating_sub(before).max(1);
+        // `after` 来自 -A/-C，是用户直接输入的 usize，传入 usize::MAX 这样的值时
+        // 普通的 `lineno + after` 会在 debug 下溢出 panic，这里用 saturating_add。
+        let end = lineno.saturating_add(after).min(all_lines.len());
+        match ranges.last_mut() {
+            Some((_, last_end)) if start <= *last_end + 1 => {
+                *last_end = end.max(*last_end);
+            }
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    let matched_set: std::collections::HashSet<usize> = matched_lines.into_iter().collect();
+
+    ranges
+        .into_iter()
+        .map(|(start, end)| MatchGroup {
+            lines: (start..=end)
+                .map(|lineno| ContextLine {
+                    lineno,
+                    text: all_lines[lineno - 1].to_string(),
+                    is_match: matched_set.contains(&lineno),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// 一个文件的搜索结果，连同它在命令行参数中出现的原始顺序一起送回主线程，
+/// 这样即便各个 worker 完成的先后顺序是乱的，输出依然能按文件分组、按输入顺序排列。
+struct FileMatch {
+    seq: usize,
+    filename: String,
+    groups: Vec<MatchGroup>,
 }
 
-pub fn search_case_insensitive<'a>(query: &str, contents: &'a str) -> Vec<&'a str> {
-    let query = query.to_lowercase();
-    let mut results = Vec::new();
+/// 递归/非递归地把命令行给出的路径展开成具体文件列表，并按展开顺序编号。
+/// 非递归模式下遇到目录直接跳过（不报错），保持和常见 grep 实现一致的行为。
+///
+/// 编号（以及最终的输出分组）按命令行参数给出的顺序排列，和 grep 的行为一致：
+/// `ioproject07 hello zzz.txt aaa.txt` 应该先打印 zzz.txt 的结果再打印 aaa.txt 的。
+/// 只有同一个参数自己展开出来的文件（比如 -r 递归进同一个目录得到的多个文件）
+/// 才在彼此之间排序，保证同一个目录下的顺序是确定的。
+fn collect_files(paths: &[String], recursive: bool) -> Vec<(usize, PathBuf)> {
+    fn walk(path: &Path, recursive: bool, files: &mut Vec<PathBuf>) {
+        if path.is_dir() {
+            if !recursive {
+                return;
+            }
+            if let Ok(entries) = fs::read_dir(path) {
+                for entry in entries.flatten() {
+                    walk(&entry.path(), recursive, files);
+                }
+            }
+        } else {
+            files.push(path.to_path_buf());
+        }
+    }
+
+    let mut files = Vec::new();
+    for path in paths {
+        let mut matches = Vec::new();
+        walk(Path::new(path), recursive, &mut matches);
+        matches.sort();
+        for path in matches {
+            files.push((files.len(), path));
+        }
+    }
+
+    files
+}
+
+/// 读取单个文件并找出它的匹配结果；IO 错误原样透传给调用方（worker），
+/// 由它决定怎么上报，这里只负责"读 + 找"这一步，方便单独测试。
+fn search_file(
+    path: &Path,
+    matcher: &Matcher,
+    case_sensitive: bool,
+    before_context: usize,
+    after_context: usize,
+) -> std::io::Result<Vec<MatchGroup>> {
+    let contents = fs::read_to_string(path)?;
+    Ok(build_match_groups(matcher, &contents, case_sensitive, before_context, after_context))
+}
+
+pub fn run(config: Config) -> Result<(), Box<dyn Error>> {
+    let matcher = Arc::new(Matcher::new(&config.query, config.case_sensitive, config.regex)?);
+    let case_sensitive = config.case_sensitive;
+    let before_context = config.before_context;
+    let after_context = config.after_context;
+
+    let files = collect_files(&config.filenames, config.recursive);
+    let total = files.len();
+    if total == 0 {
+        return Ok(());
+    }
+
+    let queue = Arc::new(Mutex::new(files.into_iter().collect::<VecDeque<_>>()));
+    // 任何一个 worker 读文件失败都要让整个命令以非零状态退出——单文件版本的
+    // 旧实现是 `fs::read_to_string(..)?` 直接把错误往上传，这里并发之后同样不能
+    // 把错误吞掉，只是多个 worker 共享同一个标记，最后由主线程统一检查一次。
+    let had_error = Arc::new(AtomicBool::new(false));
+    let (tx, rx) = mpsc::channel();
+
+    // 共享任务队列 + mpsc 回传结果，这是书中"无畏并发"一章描述的经典线程池形态：
+    // 每个 worker 不断从队列里抢文件来读、来 search，直到队列见底。
+    let worker_count = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4)
+        .min(total);
+
+    let mut workers = Vec::with_capacity(worker_count);
+    for _ in 0..worker_count {
+        let queue = Arc::clone(&queue);
+        let matcher = Arc::clone(&matcher);
+        let had_error = Arc::clone(&had_error);
+        let tx = tx.clone();
+
+        workers.push(thread::spawn(move || loop {
+            let next = queue.lock().unwrap().pop_front();
+            let (seq, path) = match next {
+                Some(item) => item,
+                None => break,
+            };
+
+            let filename = path.display().to_string();
+            let groups = match search_file(&path, &matcher, case_sensitive, before_context, after_context) {
+                Ok(groups) => groups,
+                Err(err) => {
+                    eprintln!("{}: {}", filename, err);
+                    had_error.store(true, Ordering::Relaxed);
+                    continue;
+                }
+            };
+
+            if groups.is_empty() {
+                continue;
+            }
+
+            tx.send(FileMatch { seq, filename, groups }).unwrap();
+        }));
+    }
+    drop(tx);
+
+    let mut results: Vec<FileMatch> = rx.into_iter().collect();
+    for worker in workers {
+        worker.join().expect("search worker panicked");
+    }
 
-    for line in contents.lines() {
-        if line.to_lowercase().contains(&query) {
-            results.push(line);
+    results.sort_by_key(|m| m.seq);
+
+    let multiple_files = total > 1;
+    let show_lineno = config.line_numbers;
+    let show_separators = before_context > 0 || after_context > 0;
+    let mut first_group = true;
+
+    for file_match in results {
+        for group in file_match.groups {
+            if show_separators && !first_group {
+                println!("--");
+            }
+            first_group = false;
+
+            for line in group.lines {
+                let separator = if line.is_match { ':' } else { '-' };
+                match (multiple_files, show_lineno) {
+                    (true, true) => println!("{}{}{}{}{}", file_match.filename, separator, line.lineno, separator, line.text),
+                    (true, false) => println!("{}{}{}", file_match.filename, separator, line.text),
+                    (false, true) => println!("{}{}{}", line.lineno, separator, line.text),
+                    (false, false) => println!("{}", line.text),
+                }
+            }
         }
     }
 
-    results
+    if had_error.load(Ordering::Relaxed) {
+        return Err("one or more files could not be read".into());
+    }
+
+    Ok(())
+}
+
+// 相比于使用这里的for循环方法，可以用使用迭代器的方法来替换
+// pub fn search<'a>(query: &str, contents: &'a str) -> Vec<&'a str> {
+//     let mut results = Vec::new();
+//     for line in contents.lines() {
+//         if line.contains(query) {
+//             results.push(line);
+//         }
+//     }
+//     results
+// }
+
+// 可以通过使用迭代器适配器方法来编写更简明的代码。这也避免了一个可变的中间 results vector 的使用。
+// 函数式编程风格倾向于最小化可变状态的数量来使代码更简洁。去掉可变状态可能会使得将来进行并行搜索的增强变得更容易，因为我们不必管理 results vector 的并发访问
+//
+// `search`/`search_case_insensitive` 现在连同每一行的 1-based 行号一起返回，
+// 并且和 `build_match_groups` 共用同一个 `search_with_lines`，不再是两套各自
+// 维护的大小写/正则匹配逻辑。
+pub fn search<'a>(matcher: &Matcher, contents: &'a str) -> Vec<(usize, &'a str)> {
+    search_with_lines(matcher, contents, true)
+}
+
+pub fn search_case_insensitive<'a>(matcher: &Matcher, contents: &'a str) -> Vec<(usize, &'a str)> {
+    search_with_lines(matcher, contents, false)
 }
-// 测试函数
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn parse_interleaves_flags_and_positional_args() {
+        let config = Config::builder()
+            .parse(vec![
+                "-n".to_string(),
+                "needle".to_string(),
+                "-r".to_string(),
+                "a.txt".to_string(),
+                "-C".to_string(),
+                "2".to_string(),
+                "b.txt".to_string(),
+            ])
+            .unwrap();
+
+        assert_eq!(config.query, "needle");
+        assert_eq!(config.filenames, vec!["a.txt", "b.txt"]);
+        assert!(config.recursive);
+        assert!(config.line_numbers);
+        assert_eq!(config.before_context, 2);
+        assert_eq!(config.after_context, 2);
+    }
+
+    #[test]
+    fn parse_missing_query_reports_missing_query() {
+        let err = Config::builder().parse(Vec::<String>::new()).unwrap_err();
+        assert!(matches!(err, ConfigError::MissingQuery));
+    }
+
+    #[test]
+    fn parse_missing_filename_reports_missing_filename() {
+        let err = Config::builder()
+            .parse(vec!["needle".to_string()])
+            .unwrap_err();
+        assert!(matches!(err, ConfigError::MissingFilename));
+    }
+
+    #[test]
+    fn parse_unknown_flag_is_reported_by_name() {
+        let err = Config::builder()
+            .parse(vec!["--bogus".to_string(), "needle".to_string(), "a.txt".to_string()])
+            .unwrap_err();
+        assert!(matches!(err, ConfigError::UnknownFlag(flag) if flag == "--bogus"));
+    }
+
+    #[test]
+    fn parse_context_flag_without_value_reports_missing_context_value() {
+        let err = Config::builder()
+            .parse(vec!["needle".to_string(), "a.txt".to_string(), "-A".to_string()])
+            .unwrap_err();
+        assert!(matches!(err, ConfigError::MissingContextValue(flag) if flag == "-A"));
+    }
+
+    #[test]
+    fn parse_context_flag_with_non_number_reports_invalid_context_value() {
+        let err = Config::builder()
+            .parse(vec![
+                "needle".to_string(),
+                "a.txt".to_string(),
+                "-A".to_string(),
+                "not-a-number".to_string(),
+            ])
+            .unwrap_err();
+        assert!(matches!(err, ConfigError::InvalidContextValue(flag) if flag == "-A"));
+    }
+
+    #[test]
+    fn parse_ignore_case_flag_and_case_insensitive_env_var_both_disable_case_sensitivity() {
+        // 这条测试和其它测试共用进程环境变量，先记下原值，结束时恢复，
+        // 避免和同一个测试二进制里其它依赖 CASE_INSENSITIVE 默认值的用例互相影响。
+        let had_env = env::var("CASE_INSENSITIVE").ok();
+        unsafe {
+            env::set_var("CASE_INSENSITIVE", "1");
+        }
+
+        let config = Config::builder()
+            .parse(vec!["-i".to_string(), "needle".to_string(), "a.txt".to_string()])
+            .unwrap();
+        assert!(!config.case_sensitive);
+
+        let config = Config::builder()
+            .parse(vec!["needle".to_string(), "a.txt".to_string()])
+            .unwrap();
+        assert!(!config.case_sensitive);
+
+        match had_env {
+            Some(value) => unsafe { env::set_var("CASE_INSENSITIVE", value) },
+            None => unsafe { env::remove_var("CASE_INSENSITIVE") },
+        }
+    }
+
     #[test]
     fn case_sensitive() {
         let query = "duct";
@@ -80,7 +536,8 @@ Rust:
 safe, fast, productive.
 Pick three.";
 
-        assert_eq!(vec!["safe, fast, productive."], search(query, contents));
+        let matcher = Matcher::new(query, true, false).unwrap();
+        assert_eq!(vec![(2, "safe, fast, productive.")], search(&matcher, contents));
     }
 
     #[test]
@@ -92,30 +549,148 @@ safe, fast, productive.
 Pick three.
 Trust me.";
 
+        let matcher = Matcher::new(query, false, false).unwrap();
         assert_eq!(
-            vec!["Rust:", "Trust me."],
-            search_case_insensitive(query, contents)
+            vec![(1, "Rust:"), (4, "Trust me.")],
+            search_case_insensitive(&matcher, contents)
         );
     }
-}
 
-// 相比于使用这里的for循环方法，可以用使用迭代器的方法来替换
-// pub fn search<'a>(query: &str, contents: &'a str) -> Vec<&'a str> {
-//     let mut results = Vec::new();
-//     for line in contents.lines() {
-//         if line.contains(query) {
-//             results.push(line);
-//         }
-//     }
-//     results
-// }
+    #[test]
+    fn regex_match() {
+        let query = r"p.ck";
+        let contents = "\
+Rust:
+safe, fast, productive.
+Pick three.";
 
-// 可以通过使用迭代器适配器方法来编写更简明的代码。这也避免了一个可变的中间 results vector 的使用。
-// 函数式编程风格倾向于最小化可变状态的数量来使代码更简洁。去掉可变状态可能会使得将来进行并行搜索的增强变得更容易，因为我们不必管理 results vector 的并发访问
-// 最终返回的是contents中的不可变引用
-pub fn search<'a>(query: &str, contents: &'a str) -> Vec<&'a str> {
-    contents.lines()
-        .filter(|line| line.contains(query))
-        .collect()
-}
+        let matcher = Matcher::new(query, false, true).unwrap();
+        assert_eq!(vec![(3, "Pick three.")], search_case_insensitive(&matcher, contents));
+    }
+
+    #[test]
+    fn context_merges_overlapping_windows() {
+        let query = "three";
+        let contents = "\
+Rust:
+safe, fast, productive.
+Pick three.
+Trust me.
+Pick three again.";
+
+        let matcher = Matcher::new(query, true, false).unwrap();
+        let groups = build_match_groups(&matcher, contents, true, 1, 1);
+
+        // 命中行 3 的窗口 [2, 4] 和命中行 5 的窗口 [4, 5] 在第 4 行重叠，合并成一个组。
+        assert_eq!(groups.len(), 1);
+        let lines: Vec<(usize, bool)> = groups[0].lines.iter().map(|l| (l.lineno, l.is_match)).collect();
+        assert_eq!(lines, vec![(2, false), (3, true), (4, false), (5, true)]);
+    }
+
+    #[test]
+    fn after_context_does_not_overflow_on_huge_value() {
+        let query = "hello";
+        let contents = "hello\nworld";
+
+        let matcher = Matcher::new(query, true, false).unwrap();
+        let groups = build_match_groups(&matcher, contents, true, 0, usize::MAX);
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].lines.len(), 2);
+    }
+
+    /// 每个测试在系统临时目录下建一个以测试名 + 进程 id 命名的子目录，
+    /// 避免并发跑的测试互相踩到同一批文件。调用方负责用完后自己清理。
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ioproject07-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn collect_files_non_recursive_skips_directories() {
+        let dir = temp_dir("non-recursive");
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("sub/b.txt"), "b").unwrap();
+
+        let files = collect_files(&[dir.to_str().unwrap().to_string()], false);
+        assert!(files.is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 
+    #[test]
+    fn collect_files_recursive_finds_nested_files() {
+        let dir = temp_dir("recursive");
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("a.txt"), "a").unwrap();
+        fs::write(dir.join("sub/b.txt"), "b").unwrap();
+
+        let files = collect_files(&[dir.to_str().unwrap().to_string()], true);
+        let mut names: Vec<String> = files
+            .iter()
+            .map(|(_, p)| p.file_name().unwrap().to_str().unwrap().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["a.txt", "b.txt"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn collect_files_preserves_argument_order_across_files() {
+        let dir = temp_dir("order");
+        let zzz = dir.join("zzz.txt");
+        let aaa = dir.join("aaa.txt");
+        fs::write(&zzz, "z").unwrap();
+        fs::write(&aaa, "a").unwrap();
+
+        // zzz.txt 在命令行里排在 aaa.txt 前面，即便字母序相反，numbering 也应该
+        // 按参数出现的顺序来，而不是被整体按文件名排序。
+        let files = collect_files(
+            &[
+                zzz.to_str().unwrap().to_string(),
+                aaa.to_str().unwrap().to_string(),
+            ],
+            false,
+        );
+
+        assert_eq!(files, vec![(0, zzz.clone()), (1, aaa.clone())]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn search_file_with_no_matches_returns_no_groups() {
+        let dir = temp_dir("no-match");
+        let path = dir.join("file.txt");
+        fs::write(&path, "nothing interesting here").unwrap();
+
+        let matcher = Matcher::new("hello", true, false).unwrap();
+        let groups = search_file(&path, &matcher, true, 0, 0).unwrap();
+        assert!(groups.is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn run_reports_error_when_a_file_cannot_be_read() {
+        let dir = temp_dir("missing-file");
+        let good = dir.join("good.txt");
+        let missing = dir.join("does-not-exist.txt");
+        fs::write(&good, "hello world").unwrap();
+
+        let config = Config::builder()
+            .parse(vec![
+                "hello".to_string(),
+                missing.to_str().unwrap().to_string(),
+                good.to_str().unwrap().to_string(),
+            ])
+            .unwrap();
+
+        assert!(run(config).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
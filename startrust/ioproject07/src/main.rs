@@ -6,9 +6,11 @@ use ioproject07::Config;
 fn main() {
     // let args: Vec<String> = env::args().collect();
 
-    // env::args() 返回的是可变所有权迭代器 ，所以可以直接在new函数中转移所有权
-    // env::args 函数返回一个迭代器！不同于将迭代器的值收集到一个 vector 中接着传递一个 slice 给 Config::new，现在我们直接将 env::args 返回的迭代器的所有权传递给 Config::new。
-    let config = Config::new(env::args()).unwrap_or_else(|err| {
+    // env::args() 返回的是可变所有权迭代器，第一项是程序名，这里先跳过它，
+    // 剩下的参数直接交给 Config::builder().parse，不用再收集成 Vec。
+    let mut args = env::args();
+    args.next();
+    let config = Config::builder().parse(args).unwrap_or_else(|err| {
         eprintln!("Problem parsing arguments: {}", err);
         process::exit(1);
     });